@@ -0,0 +1,396 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A pluggable replacement for the hardcoded `type_guess` ladder (bool → u64 →
+//! f32 → `"<n> <unit>"` split → str). A [`Coercion`] is tried in order by a
+//! [`CoercionSet`]; the first one to recognize a value wins, and the set falls
+//! back to a plain string when none match. Ship the original rules as the
+//! default set, but let callers opt into a different set (or an empty,
+//! strings-only one) via [`crate::element::ElementSerializer`].
+
+use serde::ser::{Serialize, Serializer};
+
+/// A scalar (or list of scalars) produced by a [`Coercion`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum CoercedValue {
+    Bool(bool),
+    U64(u64),
+    F32(f32),
+    Str(String),
+    List(Vec<CoercedValue>),
+    Null,
+}
+
+impl Serialize for CoercedValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            CoercedValue::Bool(value) => serializer.serialize_bool(*value),
+            CoercedValue::U64(value) => serializer.serialize_u64(*value),
+            CoercedValue::F32(value) => serializer.serialize_f32(*value),
+            CoercedValue::Str(value) => serializer.serialize_str(value),
+            CoercedValue::List(items) => items.serialize(serializer),
+            CoercedValue::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+/// One rule in a [`CoercionSet`]'s ladder.
+///
+/// `coerce` returns `None` to fall through to the next rule (or the final
+/// string fallback), or `Some` entries to emit under the map being built. A
+/// rule may emit more than one entry, the way the original unit-split rule
+/// turns `"200 MG"` into `dose` and `dose_unit`.
+pub trait Coercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>>;
+}
+
+/// Recognizes `true`/`false`.
+pub struct BoolCoercion;
+
+impl Coercion for BoolCoercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        val.parse::<bool>()
+            .ok()
+            .map(|value| vec![(key.to_owned(), CoercedValue::Bool(value))])
+    }
+}
+
+/// Recognizes unsigned integers, e.g. `"0"`, `"42"`.
+pub struct U64Coercion;
+
+impl Coercion for U64Coercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        val.parse::<u64>()
+            .ok()
+            .map(|value| vec![(key.to_owned(), CoercedValue::U64(value))])
+    }
+}
+
+/// Recognizes floats, e.g. `"0.1"`, `".0"`.
+pub struct F32Coercion;
+
+impl Coercion for F32Coercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        val.parse::<f32>()
+            .ok()
+            .map(|value| vec![(key.to_owned(), CoercedValue::F32(value))])
+    }
+}
+
+/// Recognizes a `"<n> <unit>"` pair, e.g. `"200 MG"`, `"20 MG/ML"`, splitting
+/// it into `{key}` and `{key}_unit`.
+pub struct UnitSplitCoercion;
+
+impl Coercion for UnitSplitCoercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        let parts: Vec<&str> = val.split_whitespace().collect();
+        if parts.len() != 2 {
+            return None;
+        }
+        let n = parts[0].parse::<f32>().ok()?;
+        let unit = parts[1].to_lowercase();
+        Some(vec![
+            (key.to_owned(), CoercedValue::F32(n)),
+            (format!("{}_unit", key), CoercedValue::Str(unit)),
+        ])
+    }
+}
+
+/// Recognizes empty strings and common null markers (`"null"`, `"nil"`,
+/// case-insensitively) as JSON `null` rather than an empty/whitespace string.
+pub struct NullCoercion;
+
+impl Coercion for NullCoercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        let trimmed = val.trim();
+        if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("null") || trimmed.eq_ignore_ascii_case("nil")
+        {
+            Some(vec![(key.to_owned(), CoercedValue::Null)])
+        } else {
+            None
+        }
+    }
+}
+
+/// Recognizes ISO-8601 dates (`2024-01-31`) and date-times
+/// (`2024-01-31T10:15:00Z`). The value is kept as a string (callers that want
+/// a real `Date`/`DateTime` type parse it downstream); this rule exists to
+/// claim the value before a looser rule might otherwise misfire on it.
+pub struct IsoDateTimeCoercion;
+
+impl Coercion for IsoDateTimeCoercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        if looks_like_iso8601(val) {
+            Some(vec![(key.to_owned(), CoercedValue::Str(val.to_owned()))])
+        } else {
+            None
+        }
+    }
+}
+
+fn looks_like_iso8601(val: &str) -> bool {
+    let bytes = val.as_bytes();
+    let is_digit = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let date_ok = val.len() >= 10
+        && (0..4).all(is_digit)
+        && bytes[4] == b'-'
+        && (5..7).all(is_digit)
+        && bytes[7] == b'-'
+        && (8..10).all(is_digit);
+    if !date_ok {
+        return false;
+    }
+    val.len() == 10 || bytes[10] == b'T' || bytes[10] == b' '
+}
+
+/// Splits comma- or space-separated values, e.g. `"a,b,c"` or `"1 2 3"`, into
+/// a list. Only fires when the separator actually produces two or more
+/// non-empty items, so a lone value still falls through to the next rule.
+pub struct ListCoercion {
+    pub separator: char,
+}
+
+impl Coercion for ListCoercion {
+    fn coerce(&self, key: &str, val: &str) -> Option<Vec<(String, CoercedValue)>> {
+        let items: Vec<CoercedValue> = val
+            .split(self.separator)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| CoercedValue::Str(s.to_owned()))
+            .collect();
+        if items.len() < 2 {
+            return None;
+        }
+        Some(vec![(key.to_owned(), CoercedValue::List(items))])
+    }
+}
+
+/// An ordered list of [`Coercion`] rules consulted by `Element::serialize`.
+/// The first rule to recognize a value wins; if none do, the value is
+/// serialized as a plain string.
+pub struct CoercionSet {
+    rules: Vec<Box<dyn Coercion>>,
+}
+
+impl CoercionSet {
+    /// Builds a set from an explicit, ordered list of rules.
+    pub fn new(rules: Vec<Box<dyn Coercion>>) -> CoercionSet {
+        CoercionSet { rules }
+    }
+
+    /// A set with no rules at all: every value is serialized as a plain
+    /// string.
+    pub fn empty() -> CoercionSet {
+        CoercionSet { rules: Vec::new() }
+    }
+
+    /// Runs `val` through the ladder, falling back to a plain string.
+    pub fn coerce(&self, key: &str, val: &str) -> Vec<(String, CoercedValue)> {
+        for rule in &self.rules {
+            if let Some(entries) = rule.coerce(key, val) {
+                return entries;
+            }
+        }
+        vec![(key.to_owned(), CoercedValue::Str(val.to_owned()))]
+    }
+
+    /// Like [`CoercionSet::coerce`], but for a bare scalar with no key (the
+    /// lone-text-body case). A rule that emits a single entry (bool, u64,
+    /// f32, ...) hands that value straight back; a rule like
+    /// [`UnitSplitCoercion`] that only makes sense keyed (it needs a
+    /// `{key}_unit` to attach the unit to) has nowhere to put its extra
+    /// entries here, so rather than silently dropping them (losing the unit
+    /// off e.g. `"200 MG"`) we fall back to the original string unchanged.
+    pub fn coerce_scalar(&self, val: &str) -> CoercedValue {
+        let mut entries = self.coerce("", val).into_iter();
+        let Some((_, value)) = entries.next() else {
+            return CoercedValue::Str(val.to_owned());
+        };
+        if entries.next().is_some() {
+            CoercedValue::Str(val.to_owned())
+        } else {
+            value
+        }
+    }
+}
+
+impl Default for CoercionSet {
+    /// The original, medical-data-tuned ladder: bool, then u64, then f32,
+    /// then a `"<n> <unit>"` split, then a plain string.
+    fn default() -> CoercionSet {
+        CoercionSet::new(vec![
+            Box::new(BoolCoercion),
+            Box::new(U64Coercion),
+            Box::new(F32Coercion),
+            Box::new(UnitSplitCoercion),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CoercedValue, Coercion, CoercionSet, IsoDateTimeCoercion, ListCoercion, NullCoercion,
+    };
+
+    #[test]
+    fn test_coerce_scalar_number() {
+        assert_eq!(
+            CoercionSet::default().coerce_scalar("42.5"),
+            CoercedValue::F32(42.5)
+        );
+    }
+
+    #[test]
+    fn test_coerce_scalar_bool() {
+        assert_eq!(
+            CoercionSet::default().coerce_scalar("true"),
+            CoercedValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_coerce_scalar_keeps_unit_attached() {
+        // No key to attach a `_unit` suffix to here, so the value must come
+        // back whole rather than dropping the unit.
+        assert_eq!(
+            CoercionSet::default().coerce_scalar("200 MG"),
+            CoercedValue::Str("200 MG".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_coerce_scalar_string_fallback() {
+        assert_eq!(
+            CoercionSet::default().coerce_scalar("Aspirin"),
+            CoercedValue::Str("Aspirin".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_coerce_splits_unit_when_keyed() {
+        let entries = CoercionSet::default().coerce("dose", "200 MG");
+        assert_eq!(
+            entries,
+            vec![
+                ("dose".to_owned(), CoercedValue::F32(200.0)),
+                ("dose_unit".to_owned(), CoercedValue::Str("mg".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_set_always_falls_back_to_string() {
+        assert_eq!(
+            CoercionSet::empty().coerce("n", "42"),
+            vec![("n".to_owned(), CoercedValue::Str("42".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn test_null_coercion_recognizes_empty_and_null_markers() {
+        let set = CoercionSet::new(vec![Box::new(NullCoercion)]);
+        assert_eq!(
+            set.coerce("n", ""),
+            vec![("n".to_owned(), CoercedValue::Null)]
+        );
+        assert_eq!(
+            set.coerce("n", "null"),
+            vec![("n".to_owned(), CoercedValue::Null)]
+        );
+        assert_eq!(
+            set.coerce("n", "NIL"),
+            vec![("n".to_owned(), CoercedValue::Null)]
+        );
+        assert_eq!(
+            set.coerce("n", "42"),
+            vec![("n".to_owned(), CoercedValue::Str("42".to_owned()))]
+        );
+    }
+
+    #[test]
+    fn test_iso_date_time_coercion_recognizes_dates_and_datetimes() {
+        let set = CoercionSet::new(vec![Box::new(IsoDateTimeCoercion)]);
+        assert_eq!(
+            set.coerce("d", "2024-01-31"),
+            vec![("d".to_owned(), CoercedValue::Str("2024-01-31".to_owned()))]
+        );
+        assert_eq!(
+            set.coerce("d", "2024-01-31T10:15:00Z"),
+            vec![(
+                "d".to_owned(),
+                CoercedValue::Str("2024-01-31T10:15:00Z".to_owned())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_iso_date_time_coercion_rejects_non_dates() {
+        assert_eq!(IsoDateTimeCoercion.coerce("d", "Aspirin"), None);
+        assert_eq!(IsoDateTimeCoercion.coerce("d", "2024-01"), None);
+    }
+
+    #[test]
+    fn test_list_coercion_splits_on_separator() {
+        let set = CoercionSet::new(vec![Box::new(ListCoercion { separator: ',' })]);
+        assert_eq!(
+            set.coerce("tags", "a,b,c"),
+            vec![(
+                "tags".to_owned(),
+                CoercedValue::List(vec![
+                    CoercedValue::Str("a".to_owned()),
+                    CoercedValue::Str("b".to_owned()),
+                    CoercedValue::Str("c".to_owned()),
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn test_list_coercion_leaves_a_lone_value_for_the_next_rule() {
+        assert_eq!(ListCoercion { separator: ',' }.coerce("tags", "solo"), None);
+    }
+
+    #[test]
+    fn test_custom_coercion_set_composes_individual_rules() {
+        let set = CoercionSet::new(vec![
+            Box::new(IsoDateTimeCoercion),
+            Box::new(NullCoercion),
+            Box::new(ListCoercion { separator: ' ' }),
+        ]);
+
+        assert_eq!(
+            set.coerce("when", "2024-01-31"),
+            vec![("when".to_owned(), CoercedValue::Str("2024-01-31".to_owned()))]
+        );
+        assert_eq!(
+            set.coerce("missing", ""),
+            vec![("missing".to_owned(), CoercedValue::Null)]
+        );
+        assert_eq!(
+            set.coerce("nums", "1 2 3"),
+            vec![(
+                "nums".to_owned(),
+                CoercedValue::List(vec![
+                    CoercedValue::Str("1".to_owned()),
+                    CoercedValue::Str("2".to_owned()),
+                    CoercedValue::Str("3".to_owned()),
+                ])
+            )]
+        );
+        // None of the three rules recognize this; falls all the way through.
+        assert_eq!(
+            set.coerce("name", "Aspirin"),
+            vec![("name".to_owned(), CoercedValue::Str("Aspirin".to_owned()))]
+        );
+    }
+}
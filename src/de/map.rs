@@ -0,0 +1,123 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::{self, value::StrDeserializer};
+use std::collections::HashMap;
+
+use super::{Error, ScalarDeserializer, SeqDeserializer, VALUE_KEY};
+use crate::element::{map_collect, Element};
+use crate::Xml;
+
+/// The element's own character/CDATA content, ignoring any text nested
+/// inside child elements. Unlike `Element::content_str`, this does not
+/// recurse, so an element that has both child elements and its own text
+/// (e.g. `<a>direct<b>nested</b></a>`) surfaces only `"direct"` under
+/// `$value`, not `"directnested"`.
+fn direct_text_str(elem: &Element) -> String {
+    let mut res = String::new();
+    for child in &elem.children {
+        if let Xml::CharacterNode(data) | Xml::CDATANode(data) = child {
+            res.push_str(data);
+        }
+    }
+    res
+}
+
+/// What `next_value_seed` should do once `next_key_seed` has picked a key.
+enum Pending<'a> {
+    Attr(&'a str),
+    Children(Vec<&'a Element>),
+    Text(String),
+}
+
+/// A `MapAccess` that yields an element's attribute names first, then its
+/// distinct child-element names, then (if any text remains) `$value`.
+pub(crate) struct MapDeserializer<'a> {
+    attrs: std::vec::IntoIter<(&'a str, &'a str)>,
+    children: std::vec::IntoIter<(&'a str, Vec<&'a Element>)>,
+    text: Option<String>,
+    pending: Option<Pending<'a>>,
+}
+
+impl<'a> MapDeserializer<'a> {
+    pub(crate) fn new(elem: &'a Element) -> Self {
+        let attrs: Vec<(&str, &str)> = elem
+            .attributes
+            .iter()
+            .map(|((name, _), value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        let mut grouped: HashMap<&str, Vec<&Element>> = HashMap::new();
+        for child in &elem.children {
+            if let Xml::ElementNode(child) = child {
+                map_collect(&mut grouped, child.name.as_str(), child);
+            }
+        }
+
+        let text = {
+            let text = direct_text_str(elem);
+            let text = text.trim();
+            if text.is_empty() {
+                None
+            } else {
+                Some(text.to_owned())
+            }
+        };
+
+        MapDeserializer {
+            attrs: attrs.into_iter(),
+            children: grouped.into_iter().collect::<Vec<_>>().into_iter(),
+            text,
+            pending: None,
+        }
+    }
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapDeserializer<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if let Some((name, value)) = self.attrs.next() {
+            self.pending = Some(Pending::Attr(value));
+            return seed
+                .deserialize(StrDeserializer::<Error>::new(name))
+                .map(Some);
+        }
+        if let Some((name, children)) = self.children.next() {
+            self.pending = Some(Pending::Children(children));
+            return seed
+                .deserialize(StrDeserializer::<Error>::new(name))
+                .map(Some);
+        }
+        if let Some(text) = self.text.take() {
+            self.pending = Some(Pending::Text(text));
+            return seed
+                .deserialize(StrDeserializer::<Error>::new(VALUE_KEY))
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.pending.take() {
+            Some(Pending::Attr(value)) => seed.deserialize(ScalarDeserializer(value)),
+            Some(Pending::Children(children)) => {
+                seed.deserialize(SeqDeserializer::new(children))
+            }
+            Some(Pending::Text(text)) => seed.deserialize(ScalarDeserializer(&text)),
+            None => Err(de::Error::custom("next_value_seed called before next_key_seed")),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A `serde::Deserializer` driven off the `Element` tree produced by
+//! `Parser`/`ElementBuilder`, split along the lines of serde-xml-rs's
+//! `de/map.rs`, `de/seq.rs` and `de/var.rs`.
+
+mod map;
+mod seq;
+mod var;
+
+use serde::de::{self, DeserializeOwned, Visitor};
+use std::fmt;
+
+use crate::coercion::{CoercedValue, CoercionSet};
+use crate::element::Element;
+use crate::stream_parser::{StreamError, StreamParser};
+
+pub(crate) use map::MapDeserializer;
+pub(crate) use seq::SeqDeserializer;
+pub(crate) use var::EnumDeserializer;
+
+/// The conventional field name under which an element's own text content is
+/// exposed, letting a struct capture attributes and a text body at once.
+const VALUE_KEY: &str = "$value";
+
+/// Errors produced while deserializing an `Element` tree into a user type.
+#[derive(Debug)]
+pub enum Error {
+    /// A custom error raised by the `Visitor`/`Deserialize` impl being driven.
+    Custom(String),
+    /// The document did not contain a top-level element to deserialize from.
+    NoElement,
+    /// Failed to parse the input into an `Element` tree in the first place.
+    Stream(StreamError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Custom(msg) => f.write_str(msg),
+            Error::NoElement => f.write_str("no element to deserialize"),
+            Error::Stream(err) => write!(f, "failed to parse XML: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(err: StreamError) -> Error {
+        Error::Stream(err)
+    }
+}
+
+/// Parses `s` and deserializes its top-level element into `T`.
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T, Error> {
+    let mut stream = StreamParser::new();
+    stream.push(s);
+    let elem = stream.next().ok_or(Error::NoElement)??;
+    T::deserialize(Deserializer::from_element(&elem))
+}
+
+/// Feeds `text` through the same [`CoercionSet`] that `Element` serialization
+/// uses and hands the result to `visitor`.
+fn deserialize_scalar<'de, V>(text: &str, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    match CoercionSet::default().coerce_scalar(text) {
+        CoercedValue::Bool(value) => visitor.visit_bool(value),
+        CoercedValue::U64(value) => visitor.visit_u64(value),
+        CoercedValue::F32(value) => visitor.visit_f32(value),
+        CoercedValue::Str(value) => visitor.visit_string(value),
+        CoercedValue::List(_) | CoercedValue::Null => visitor.visit_str(text),
+    }
+}
+
+/// A `Deserializer` over a leaf text value, driven off [`CoercionSet`].
+pub(crate) struct ScalarDeserializer<'a>(pub(crate) &'a str);
+
+impl<'de, 'a> de::Deserializer<'de> for ScalarDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        deserialize_scalar(self.0, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// A `serde::Deserializer` over a single `Element`, used both as the entry point
+/// for [`from_str`] and recursively for nested struct/seq fields.
+pub struct Deserializer<'a> {
+    elem: &'a Element,
+}
+
+impl<'a> Deserializer<'a> {
+    /// Wraps an already-parsed `Element` for deserialization.
+    pub(crate) fn from_element(elem: &'a Element) -> Self {
+        Deserializer { elem }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let has_child_elem = self
+            .elem
+            .children
+            .iter()
+            .any(|c| matches!(c, crate::Xml::ElementNode(_)));
+
+        if self.elem.attributes.len() == 0 && !has_child_elem {
+            let text = self.elem.content_str();
+            return deserialize_scalar(text.trim(), visitor);
+        }
+
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(self.elem))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let items: Vec<&Element> = self
+            .elem
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                crate::Xml::ElementNode(e) => Some(e),
+                _ => None,
+            })
+            .collect();
+        visitor.visit_seq(SeqDeserializer::new(items))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(EnumDeserializer::new(self.elem))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct tuple
+        tuple_struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::from_str;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item {
+        #[serde(rename = "$value")]
+        value: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Items {
+        item: Vec<Item>,
+    }
+
+    #[test]
+    fn test_single_occurrence_deserializes_as_one_element_vec() {
+        let items: Items = from_str("<items><item>a</item></items>").unwrap();
+        assert_eq!(
+            items,
+            Items {
+                item: vec![Item { value: "a".to_owned() }]
+            }
+        );
+    }
+
+    #[test]
+    fn test_repeated_occurrence_deserializes_as_vec() {
+        let items: Items = from_str("<items><item>a</item><item>b</item></items>").unwrap();
+        assert_eq!(
+            items,
+            Items {
+                item: vec![
+                    Item { value: "a".to_owned() },
+                    Item { value: "b".to_owned() },
+                ]
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Wrapper {
+        item: Item,
+    }
+
+    #[test]
+    fn test_single_occurrence_deserializes_as_singular_struct() {
+        let wrapper: Wrapper = from_str("<wrapper><item>a</item></wrapper>").unwrap();
+        assert_eq!(
+            wrapper,
+            Wrapper {
+                item: Item { value: "a".to_owned() }
+            }
+        );
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct WithValue {
+        #[serde(rename = "$value")]
+        value: String,
+    }
+
+    #[test]
+    fn test_value_excludes_nested_element_text() {
+        let elem: WithValue = from_str("<a>direct<b>nested</b></a>").unwrap();
+        assert_eq!(
+            elem,
+            WithValue {
+                value: "direct".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_returns_err() {
+        let result: Result<Item, _> = from_str("<a><b></a>");
+        assert!(result.is_err());
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Status {
+        Success,
+        Error(ErrorDetail),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct ErrorDetail {
+        #[serde(rename = "$value")]
+        message: String,
+    }
+
+    #[test]
+    fn test_enum_dispatches_on_element_name_for_unit_variant() {
+        let status: Status = from_str("<Success/>").unwrap();
+        assert_eq!(status, Status::Success);
+    }
+
+    #[test]
+    fn test_enum_dispatches_on_element_name_for_newtype_variant() {
+        let status: Status = from_str("<Error>oops</Error>").unwrap();
+        assert_eq!(
+            status,
+            Status::Error(ErrorDetail {
+                message: "oops".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::{self, Visitor};
+
+use super::{Deserializer, Error};
+use crate::element::Element;
+
+/// A `SeqAccess` over a fixed list of elements.
+struct SeqAccessImpl<'a> {
+    iter: std::vec::IntoIter<&'a Element>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccessImpl<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(elem) => seed.deserialize(Deserializer::from_element(elem)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// A `Deserializer`/`SeqAccess` over the child elements sharing a name,
+/// whether there turn out to be one of them or several.
+///
+/// A single XML occurrence of a "maybe repeated" element (e.g. a lone
+/// `<item>` under `<items>`) is indistinguishable, just from the `Element`
+/// tree, from a field that is genuinely singular. The destination type is
+/// what disambiguates: `deserialize_seq` (driven by `Vec<T>`) always treats
+/// `elems` as a sequence, even a one-element one, while every other type
+/// hint (`struct`, `map`, a bare scalar, ...) delegates to the single
+/// element's own `Deserializer` and errors if there isn't exactly one.
+pub(crate) struct SeqDeserializer<'a> {
+    elems: Vec<&'a Element>,
+}
+
+impl<'a> SeqDeserializer<'a> {
+    pub(crate) fn new(elems: Vec<&'a Element>) -> Self {
+        SeqDeserializer { elems }
+    }
+
+    fn into_seq_access(self) -> SeqAccessImpl<'a> {
+        SeqAccessImpl {
+            iter: self.elems.into_iter(),
+        }
+    }
+
+    /// The single element to delegate to for a non-seq type hint.
+    fn only(mut self) -> Result<Deserializer<'a>, Error> {
+        if self.elems.len() == 1 {
+            Ok(Deserializer::from_element(self.elems.pop().unwrap()))
+        } else {
+            Err(de::Error::custom(format!(
+                "expected a single element but found {}",
+                self.elems.len()
+            )))
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for SeqDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.elems.len() == 1 {
+            self.only()?.deserialize_any(visitor)
+        } else {
+            visitor.visit_seq(self.into_seq_access())
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(self.into_seq_access())
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.only()?.deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.only()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.only()?.deserialize_option(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.only()?.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct tuple
+        tuple_struct identifier ignored_any
+    }
+}
@@ -0,0 +1,76 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::{self, value::StrDeserializer, Visitor};
+
+use super::{Deserializer, Error};
+use crate::element::Element;
+
+/// An `EnumAccess` that dispatches on the element's own name: `<Success/>`
+/// picks the `Success` variant, with the element itself as its payload.
+pub(crate) struct EnumDeserializer<'a> {
+    elem: &'a Element,
+}
+
+impl<'a> EnumDeserializer<'a> {
+    pub(crate) fn new(elem: &'a Element) -> Self {
+        EnumDeserializer { elem }
+    }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumDeserializer<'a> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let name = StrDeserializer::<Error>::new(&self.elem.name);
+        let value = seed.deserialize(name)?;
+        Ok((value, VariantDeserializer { elem: self.elem }))
+    }
+}
+
+pub(crate) struct VariantDeserializer<'a> {
+    elem: &'a Element,
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for VariantDeserializer<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(Deserializer::from_element(self.elem))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(Deserializer::from_element(self.elem), visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(Deserializer::from_element(self.elem), visitor)
+    }
+}
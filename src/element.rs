@@ -7,13 +7,15 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use serde::de::{IgnoredAny, Visitor};
+use serde::de::Visitor;
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
 
-use crate::element_builder::{BuilderError, ElementBuilder};
-use crate::parser::{Parser, Pos};
-use crate::{escape, AttrMap, Xml};
+use crate::coercion::CoercionSet;
+use crate::element_builder::BuilderError;
+use crate::ns_choice::NSChoice;
+use crate::stream_parser::StreamError;
+use crate::{AttrMap, Xml};
 
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -50,63 +52,43 @@ pub fn map_collect<K: Hash + Eq, V>(map: &mut HashMap<K, Vec<V>>, k: K, val: V)
 }
 
 impl<'de> Deserialize<'de> for Element {
+    /// Deserializes through the same lossless, round-trippable shape used by
+    /// [`crate::lossless::OwnedLosslessElement`], so an `Element` embedded as
+    /// a field (e.g. `#[derive(Deserialize)] struct Wrapper { elem: Element
+    /// }`, fed through `serde_json`/`toml`/...) comes back intact instead of
+    /// a placeholder. This is not the inverse of `Element`'s own lossy,
+    /// Elasticsearch-style `Serialize` impl below, whose output cannot be read
+    /// back here; serialize through [`crate::lossless::LosslessElement`]
+    /// explicitly when you need symmetric `Serialize`/`Deserialize`.
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer
-            .deserialize_ignored_any(IgnoredAny)
-            .map(|x| Element::new("todo".to_owned(), None, vec![]))
+        crate::lossless::OwnedLosslessElement::deserialize(deserializer).map(|owned| owned.0)
     }
 }
 
-/// Produces one or more entries
+/// Produces one or more entries, consulting `coercions` in order and falling
+/// back to a plain string when none of them recognize `val`.
 pub fn type_guess<S: serde::Serializer>(
     key: &str,
     val: &str,
+    coercions: &CoercionSet,
     map: &mut S::SerializeMap,
 ) -> Result<(), S::Error> {
-    // Try parsing as f32, for examples like "0", "0.1", ".0"
-    if let Ok(value) = val.parse::<bool>() {
-        map.serialize_entry(key, &value)?;
-        return Ok(());
-    }
-    if let Ok(value) = val.parse::<u64>() {
-        map.serialize_entry(key, &value)?;
-        return Ok(());
-    }
-    if let Ok(value) = val.parse::<f32>() {
-        map.serialize_entry(key, &value)?;
-        return Ok(());
-    }
-
-    // Try parsing as two fields, for input like "200 MG" "200 mg" "100 mg/1" "20 MG/ML"
-    let parts: Vec<&str> = val.split_whitespace().collect();
-    if parts.len() == 2 {
-        if let Ok(n) = parts[0].parse::<f32>() {
-            let denom = parts[1].to_lowercase();
-            map.serialize_entry(key, &n)?;
-            map.serialize_entry(&format!("{}_unit", key), &denom)?;
-            return Ok(());
-        }
-    } else {
-        info!("parts {:?}", &parts);
+    for (key, value) in coercions.coerce(key, val) {
+        info!("{}, {:?}", key, value);
+        map.serialize_entry(&key, &value)?;
     }
-    info!("{}, {}", key, val);
-    map.serialize_entry(key, val)
+    Ok(())
 }
 
-pub fn type_guess_val<S: serde::Serializer>(val: &str, s: S) -> Result<S::Ok, S::Error> {
-    // Try parsing as f32, for examples like "0", "0.1", ".0"
-    if let Ok(value) = val.parse::<u64>() {
-        s.serialize_u64(value)
-    } else if let Ok(value) = val.parse::<bool>() {
-        s.serialize_bool(value)
-    } else if let Ok(value) = val.parse::<f32>() {
-        s.serialize_f32(value)
-    } else {
-        s.serialize_str(val)
-    }
+pub fn type_guess_val<S: serde::Serializer>(
+    val: &str,
+    coercions: &CoercionSet,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    coercions.coerce_scalar(val).serialize(s)
 }
 
 // All entries are handled like key:[val]
@@ -116,140 +98,123 @@ impl Serialize for Element {
     where
         S: serde::Serializer,
     {
-        /*
-           element.name: {
-               ..atttrs,
-               ..children
-           }
-        */
-
-        if self.attributes.len() == 0 && self.children.len() == 0 {
-            return serializer.serialize_unit();
-        }
-        let attr_num = self.attributes.len() + self.children.len();
-
-        let mut elements = HashMap::new();
-        let mut comments = Vec::new();
-        let mut texts = Vec::new();
-        for kid in &self.children {
-            match kid {
-                Xml::ElementNode(el) => map_collect(&mut elements, el.name.clone(), el),
-                Xml::CommentNode(c) => comments.push(c),
-                Xml::CharacterNode(text) => {
-                    let t = text.trim();
-                    if !t.is_empty() {
-                        texts.push(t)
-                    }
+        serialize_with_coercions(self, &CoercionSet::default(), serializer)
+    }
+}
+
+/// The body of `Serialize for Element`, factored out so [`ElementSerializer`]
+/// can drive it with a caller-chosen [`CoercionSet`] instead of the default.
+fn serialize_with_coercions<S>(
+    elem: &Element,
+    coercions: &CoercionSet,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    /*
+       element.name: {
+           ..atttrs,
+           ..children
+       }
+    */
+
+    if elem.attributes.len() == 0 && elem.children.len() == 0 {
+        return serializer.serialize_unit();
+    }
+    let attr_num = elem.attributes.len() + elem.children.len();
+
+    let mut elements = HashMap::new();
+    let mut comments = Vec::new();
+    let mut texts = Vec::new();
+    for kid in &elem.children {
+        match kid {
+            Xml::ElementNode(el) => map_collect(&mut elements, el.name.clone(), el),
+            Xml::CommentNode(c) => comments.push(c),
+            Xml::CharacterNode(text) => {
+                let t = text.trim();
+                if !t.is_empty() {
+                    texts.push(t)
                 }
-                _ => continue, // unsound, too lazy
-            };
-        }
-        if elements.len() == 0 && comments.len() == 0 && self.attributes.len() == 0 {
-            if texts.len() == 1 {
-                type_guess_val(&texts[0], serializer)
-            } else {
-                texts.serialize(serializer)
             }
+            _ => continue, // unsound, too lazy
+        };
+    }
+    if elements.len() == 0 && comments.len() == 0 && elem.attributes.len() == 0 {
+        if texts.len() == 1 {
+            type_guess_val(&texts[0], coercions, serializer)
         } else {
-            let mut mapper = serializer.serialize_map(Some(attr_num))?;
-            for ((key, _no_idea), val) in &self.attributes {
-                type_guess::<S>(&key, &val, &mut mapper)?;
-            }
+            texts.serialize(serializer)
+        }
+    } else {
+        let mut mapper = serializer.serialize_map(Some(attr_num))?;
+        for ((key, _no_idea), val) in &elem.attributes {
+            type_guess::<S>(key, val, coercions, &mut mapper)?;
+        }
 
-            for (key, vec) in elements {
-                match vec.len() {
-                    0 => (),
-                    1 => mapper.serialize_entry(&key, &vec[0])?,
-                    _ => mapper.serialize_entry(&key, &vec)?,
-                };
-            }
-            match comments.len() {
-                0 => (),
-                1 => mapper.serialize_entry("_comment", &comments[0])?,
-                _ => mapper.serialize_entry("_comment", &comments)?,
-            };
-            match texts.len() {
+        for (key, vec) in elements {
+            match vec.len() {
                 0 => (),
-                1 => mapper.serialize_entry("_body", &texts[0])?,
-                _ => mapper.serialize_entry("_body", &texts)?,
+                1 => mapper.serialize_entry(&key, &vec[0])?,
+                _ => mapper.serialize_entry(&key, &vec)?,
             };
-            mapper.end()
         }
+        match comments.len() {
+            0 => (),
+            1 => mapper.serialize_entry("_comment", &comments[0])?,
+            _ => mapper.serialize_entry("_comment", &comments)?,
+        };
+        match texts.len() {
+            0 => (),
+            1 => mapper.serialize_entry("_body", &texts[0])?,
+            _ => mapper.serialize_entry("_body", &texts)?,
+        };
+        mapper.end()
     }
 }
 
-fn fmt_elem(
-    elem: &Element,
-    parent: Option<&Element>,
-    all_prefixes: &HashMap<String, String>,
-    f: &mut fmt::Formatter,
-) -> fmt::Result {
-    let mut all_prefixes = all_prefixes.clone();
-    all_prefixes.extend(elem.prefixes.clone().into_iter());
-
-    // Do we need a prefix?
-    if elem.ns != elem.default_ns {
-        let prefix = all_prefixes
-            .get(elem.ns.as_ref().map_or("", |x| &x[..]))
-            .expect("No namespace prefix bound");
-        write!(f, "<{}:{}", *prefix, elem.name)?;
-    } else {
-        write!(f, "<{}", elem.name)?;
-    }
+/// Serializes an `Element` with a caller-chosen [`CoercionSet`] instead of the
+/// default ladder, e.g. `CoercionSet::empty()` for a strings-only mode, or a
+/// custom set with ISO-8601/null/list rules mixed in.
+///
+/// ```ignore
+/// let json = serde_json::to_string(&elem.with_coercions(CoercionSet::empty()))?;
+/// ```
+pub struct ElementSerializer<'a> {
+    elem: &'a Element,
+    coercions: CoercionSet,
+}
 
-    // Do we need to set the default namespace ?
-    if !elem
-        .attributes
-        .iter()
-        .any(|(&(ref name, _), _)| name == "xmlns")
-    {
-        match (parent, &elem.default_ns) {
-            // No parent, namespace is not empty
-            (None, &Some(ref ns)) => write!(f, " xmlns='{}'", *ns)?,
-            // Parent and child namespace differ
-            (Some(parent), ns) if parent.default_ns != *ns => {
-                write!(f, " xmlns='{}'", ns.as_ref().map_or("", |x| &x[..]))?
-            }
-            _ => (),
+impl<'a> ElementSerializer<'a> {
+    /// Starts from the default coercion ladder; chain [`ElementSerializer::coercions`]
+    /// to replace it.
+    pub fn new(elem: &'a Element) -> ElementSerializer<'a> {
+        ElementSerializer {
+            elem,
+            coercions: CoercionSet::default(),
         }
     }
 
-    for (&(ref name, ref ns), value) in &elem.attributes {
-        match *ns {
-            Some(ref ns) => {
-                let prefix = all_prefixes.get(ns).expect("No namespace prefix bound");
-                write!(f, " {}:{}='{}'", *prefix, name, escape(value))?
-            }
-            None => write!(f, " {}='{}'", name, escape(value))?,
-        }
+    /// Replaces the coercion ladder used while serializing.
+    pub fn coercions(mut self, coercions: CoercionSet) -> ElementSerializer<'a> {
+        self.coercions = coercions;
+        self
     }
+}
 
-    if elem.children.is_empty() {
-        write!(f, "/>")?;
-    } else {
-        write!(f, ">")?;
-        for child in &elem.children {
-            match *child {
-                Xml::ElementNode(ref child) => fmt_elem(child, Some(elem), &all_prefixes, f)?,
-                ref o => fmt::Display::fmt(o, f)?,
-            }
-        }
-        if elem.ns != elem.default_ns {
-            let prefix = all_prefixes
-                .get(elem.ns.as_ref().unwrap())
-                .expect("No namespace prefix bound");
-            write!(f, "</{}:{}>", *prefix, elem.name)?;
-        } else {
-            write!(f, "</{}>", elem.name)?;
-        }
+impl<'a> Serialize for ElementSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serialize_with_coercions(self.elem, &self.coercions, serializer)
     }
-
-    Ok(())
 }
 
 impl fmt::Display for Element {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt_elem(self, None, &HashMap::new(), f)
+        crate::writer::write_to(self, &mut crate::writer::FmtAdapter(f))
+            .map_err(|_| fmt::Error)
     }
 }
 
@@ -257,17 +222,17 @@ impl fmt::Display for Element {
 pub struct ChildElements<'a, 'b> {
     elems: slice::Iter<'a, Xml>,
     name: &'b str,
-    ns: Option<&'b str>,
+    ns: NSChoice,
 }
 
 impl<'a, 'b> Iterator for ChildElements<'a, 'b> {
     type Item = &'a Element;
 
     fn next(&mut self) -> Option<&'a Element> {
-        let (name, ns) = (self.name, self.ns);
+        let (name, ns) = (self.name, &self.ns);
         self.elems.by_ref().find_map(|child| {
             if let Xml::ElementNode(ref elem) = *child {
-                if name == elem.name && ns == elem.ns.as_ref().map(|x| &x[..]) {
+                if name == elem.name && ns.matches(elem.ns.as_deref()) {
                     return Some(elem);
                 }
             }
@@ -349,7 +314,7 @@ impl Element {
 
     /// Gets the first child `Element` with the specified name and namespace. When no child
     /// with the specified name exists `None` is returned.
-    pub fn get_child<'a>(&'a self, name: &str, ns: Option<&str>) -> Option<&'a Element> {
+    pub fn get_child<'a>(&'a self, name: &str, ns: impl Into<NSChoice>) -> Option<&'a Element> {
         self.get_children(name, ns).next()
     }
 
@@ -358,15 +323,25 @@ impl Element {
     pub fn get_children<'a, 'b>(
         &'a self,
         name: &'b str,
-        ns: Option<&'b str>,
+        ns: impl Into<NSChoice>,
     ) -> ChildElements<'a, 'b> {
         ChildElements {
             elems: self.children.iter(),
             name,
-            ns,
+            ns: ns.into(),
         }
     }
 
+    /// Returns whether this element's name and namespace match `name`/`ns`.
+    pub fn is(&self, name: &str, ns: impl Into<NSChoice>) -> bool {
+        self.name == name && self.has_ns(ns)
+    }
+
+    /// Returns whether this element's namespace matches `ns`.
+    pub fn has_ns(&self, ns: impl Into<NSChoice>) -> bool {
+        ns.into().matches(self.ns.as_deref())
+    }
+
     /// Appends a child element. Returns a reference to the added element.
     pub fn tag(&mut self, child: Element) -> &mut Element {
         self.children.push(Xml::ElementNode(child));
@@ -405,27 +380,42 @@ impl Element {
         self.children.push(Xml::PINode(text));
         self
     }
+
+    /// Wraps this element for serialization with a caller-chosen [`CoercionSet`]
+    /// instead of the default bool/u64/f32/unit-split ladder.
+    pub fn with_coercions(&self, coercions: CoercionSet) -> ElementSerializer<'_> {
+        ElementSerializer::new(self).coercions(coercions)
+    }
+
+    /// Streams this element (and its children) to `w`, without buffering the
+    /// rendered document the way `to_string` via `fmt::Display` would.
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        crate::writer::write_to(self, w)
+    }
+
+    /// Like [`Element::write_to`], prefixed with an `<?xml version="1.0"
+    /// encoding="UTF-8"?>` declaration.
+    pub fn write_to_decl<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        crate::writer::write_to_decl(self, w)
+    }
 }
 
 impl FromStr for Element {
-    type Err = BuilderError;
+    type Err = StreamError;
     #[inline]
-    fn from_str(data: &str) -> Result<Element, BuilderError> {
-        todo!();
-
-        let mut p = Parser::new();
-        let mut e = ElementBuilder::new();
-
-        p.feed_str(data);
-        // TODO: Panics
-        p.find_map(|x| e.handle_event(x.unwrap().0))
-            .unwrap_or(Err(BuilderError::NoElement))
+    fn from_str(data: &str) -> Result<Element, StreamError> {
+        let mut stream = crate::stream_parser::StreamParser::new();
+        stream.push(data);
+        stream
+            .next()
+            .unwrap_or(Err(StreamError::Builder(BuilderError::NoElement)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Element;
+    use crate::coercion::CoercionSet;
     use serde::ser::{SerializeMap, Serializer};
     use std::collections::HashMap;
 
@@ -498,7 +488,7 @@ mod tests {
         let mut result = HashMap::new();
         let mut map = serde_test::MapSerializer::new(&mut result);
 
-        super::type_guess("dose", "42.5", &mut map).unwrap();
+        super::type_guess("dose", "42.5", &CoercionSet::default(), &mut map).unwrap();
 
         assert_eq!(result.get("dose"), Some(&serde_test::Token::F32(42.5)));
         assert_eq!(result.len(), 1);
@@ -509,7 +499,7 @@ mod tests {
         let mut result = HashMap::new();
         let mut map = serde_test::MapSerializer::new(&mut result);
 
-        super::type_guess("dose", "200 MG", &mut map).unwrap();
+        super::type_guess("dose", "200 MG", &CoercionSet::default(), &mut map).unwrap();
 
         assert_eq!(result.get("dose"), Some(&serde_test::Token::F32(200.0)));
         assert_eq!(result.get("dose_unit"), Some(&serde_test::Token::Str("mg")));
@@ -521,7 +511,7 @@ mod tests {
         let mut result = HashMap::new();
         let mut map = serde_test::MapSerializer::new(&mut result);
 
-        super::type_guess("name", "Aspirin", &mut map).unwrap();
+        super::type_guess("name", "Aspirin", &CoercionSet::default(), &mut map).unwrap();
 
         assert_eq!(result.get("name"), Some(&serde_test::Token::Str("Aspirin")));
         assert_eq!(result.len(), 1);
@@ -532,7 +522,7 @@ mod tests {
         let mut result = HashMap::new();
         let mut map = serde_test::MapSerializer::new(&mut result);
 
-        super::type_guess("dose", "100 Mg", &mut map).unwrap();
+        super::type_guess("dose", "100 Mg", &CoercionSet::default(), &mut map).unwrap();
 
         assert_eq!(result.get("dose"), Some(&serde_test::Token::F32(100.0)));
         assert_eq!(result.get("dose_unit"), Some(&serde_test::Token::Str("mg")));
@@ -544,9 +534,24 @@ mod tests {
         let mut result = HashMap::new();
         let mut map = serde_test::MapSerializer::new(&mut result);
 
-        super::type_guess("dose", "abc MG", &mut map).unwrap();
+        super::type_guess("dose", "abc MG", &CoercionSet::default(), &mut map).unwrap();
 
         assert_eq!(result.get("dose"), Some(&serde_test::Token::Str("abc MG")));
         assert_eq!(result.len(), 1);
     }
+
+    #[test]
+    fn test_deserialize_element_embedded_in_a_generic_struct() {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            elem: Element,
+        }
+
+        let elem: Element = "<a href='/'><b>text</b></a>".parse().unwrap();
+        let json =
+            serde_json::to_string(&crate::lossless::LosslessElement(&elem)).unwrap();
+        let wrapper: Wrapper = serde_json::from_str(&format!("{{\"elem\":{}}}", json)).unwrap();
+
+        assert_eq!(wrapper.elem, elem);
+    }
 }
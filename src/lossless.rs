@@ -0,0 +1,184 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A perfect-fidelity serialization path for `Element`, alongside the lossy
+//! Elasticsearch-style `Serialize for Element` used for search indexing.
+//!
+//! Wrapping an `&Element` in [`LosslessElement`] preserves node
+//! order, node kind (element/text/cdata/comment/PI), raw attribute strings and
+//! namespace URIs/prefixes, so the serialized form carries everything needed
+//! to reconstruct an identical `Element` by deserializing it back into an
+//! [`OwnedLosslessElement`].
+
+use serde::de::Deserializer;
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::element::Element;
+use crate::Xml;
+
+/// A node in the lossless tree, one variant per `Xml` kind.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data")]
+enum LosslessNode {
+    Element(LosslessElementData),
+    Text(String),
+    CData(String),
+    Comment(String),
+    PI(String),
+}
+
+/// The faithful, serializable representation of an `Element`: every field
+/// `Element` itself carries, so it round-trips without loss.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct LosslessElementData {
+    name: String,
+    ns: Option<String>,
+    default_ns: Option<String>,
+    prefixes: Vec<(String, String)>,
+    attributes: Vec<LosslessAttribute>,
+    children: Vec<LosslessNode>,
+}
+
+/// A single raw attribute, keeping its name, namespace and value apart rather
+/// than merging them into a map as the lossy `Serialize` impl does.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct LosslessAttribute {
+    name: String,
+    ns: Option<String>,
+    value: String,
+}
+
+impl From<&Element> for LosslessElementData {
+    fn from(elem: &Element) -> LosslessElementData {
+        LosslessElementData {
+            name: elem.name.clone(),
+            ns: elem.ns.clone(),
+            default_ns: elem.default_ns.clone(),
+            prefixes: elem.prefixes.clone().into_iter().collect(),
+            attributes: elem
+                .attributes
+                .iter()
+                .map(|((name, ns), value)| LosslessAttribute {
+                    name: name.clone(),
+                    ns: ns.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            children: elem.children.iter().map(LosslessNode::from).collect(),
+        }
+    }
+}
+
+impl From<&Xml> for LosslessNode {
+    fn from(node: &Xml) -> LosslessNode {
+        match node {
+            Xml::ElementNode(elem) => LosslessNode::Element(LosslessElementData::from(elem)),
+            Xml::CharacterNode(text) => LosslessNode::Text(text.clone()),
+            Xml::CDATANode(text) => LosslessNode::CData(text.clone()),
+            Xml::CommentNode(text) => LosslessNode::Comment(text.clone()),
+            Xml::PINode(text) => LosslessNode::PI(text.clone()),
+        }
+    }
+}
+
+impl From<LosslessElementData> for Element {
+    fn from(data: LosslessElementData) -> Element {
+        Element {
+            name: data.name,
+            ns: data.ns,
+            default_ns: data.default_ns,
+            prefixes: data.prefixes.into_iter().collect(),
+            attributes: data
+                .attributes
+                .into_iter()
+                .map(|attr| ((attr.name, attr.ns), attr.value))
+                .collect(),
+            children: data.children.into_iter().map(Xml::from).collect(),
+        }
+    }
+}
+
+impl From<LosslessNode> for Xml {
+    fn from(node: LosslessNode) -> Xml {
+        match node {
+            LosslessNode::Element(data) => Xml::ElementNode(Element::from(data)),
+            LosslessNode::Text(text) => Xml::CharacterNode(text),
+            LosslessNode::CData(text) => Xml::CDATANode(text),
+            LosslessNode::Comment(text) => Xml::CommentNode(text),
+            LosslessNode::PI(text) => Xml::PINode(text),
+        }
+    }
+}
+
+/// A newtype wrapping `&Element` that serializes without losing any
+/// structure, for callers who need canonical round-tripping — signing,
+/// diffing, re-emitting — instead of the lossy map form. It only serializes;
+/// deserialize the result through [`OwnedLosslessElement`].
+pub struct LosslessElement<'a>(pub &'a Element);
+
+impl<'a> Serialize for LosslessElement<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        LosslessElementData::from(self.0).serialize(serializer)
+    }
+}
+
+/// An owned, deserializable counterpart to [`LosslessElement`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct OwnedLosslessElement(pub Element);
+
+impl<'de> Deserialize<'de> for OwnedLosslessElement {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        LosslessElementData::deserialize(deserializer).map(|data| OwnedLosslessElement(data.into()))
+    }
+}
+
+impl Element {
+    /// Serializes this element losslessly: node order, node kind, raw
+    /// attribute strings and namespace prefixes are all preserved, so the
+    /// result deserializes back into an identical `Element` (see
+    /// [`LosslessElement`]). Contrast with the lossy, Elasticsearch-style
+    /// `Serialize` impl used for search indexing.
+    pub fn serialize_faithful<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        LosslessElement(self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LosslessElement, OwnedLosslessElement};
+    use crate::element::Element;
+
+    fn round_trip(elem: &Element) -> Element {
+        let json = serde_json::to_string(&LosslessElement(elem)).unwrap();
+        serde_json::from_str::<OwnedLosslessElement>(&json).unwrap().0
+    }
+
+    #[test]
+    fn test_round_trips_children_and_text() {
+        let elem: Element = "<a href='/'><b>text</b><c/></a>".parse().unwrap();
+        assert_eq!(round_trip(&elem), elem);
+    }
+
+    #[test]
+    fn test_round_trips_cdata() {
+        let mut elem = Element::new("a".to_owned(), None, vec![]);
+        elem.cdata("raw <stuff>".to_owned());
+        assert_eq!(round_trip(&elem), elem);
+    }
+}
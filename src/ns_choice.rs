@@ -0,0 +1,113 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Namespace-flexible matching for child lookups, ported from minidom's
+//! `NSChoice`. Real-world documents are often sloppy about declaring (or
+//! re-declaring) namespaces, so exact `Option<&str>` matching is frequently
+//! too strict for `get_child`/`get_children`.
+
+/// How to match an element's namespace.
+#[derive(Clone, PartialEq, Debug)]
+pub enum NSChoice {
+    /// Matches only elements with no namespace.
+    None,
+    /// Matches only elements in exactly this namespace.
+    OneOf(String),
+    /// Matches elements in any of these namespaces.
+    AnyOf(Vec<String>),
+    /// Matches elements in any namespace, including none.
+    Any,
+}
+
+impl NSChoice {
+    /// Returns whether `ns` satisfies this choice.
+    pub fn matches(&self, ns: Option<&str>) -> bool {
+        match self {
+            NSChoice::None => ns.is_none(),
+            NSChoice::OneOf(want) => ns == Some(want.as_str()),
+            NSChoice::AnyOf(choices) => ns.is_some_and(|ns| choices.iter().any(|c| c == ns)),
+            NSChoice::Any => true,
+        }
+    }
+}
+
+impl From<Option<&str>> for NSChoice {
+    fn from(ns: Option<&str>) -> NSChoice {
+        match ns {
+            Some(ns) => NSChoice::OneOf(ns.to_owned()),
+            None => NSChoice::None,
+        }
+    }
+}
+
+impl From<&str> for NSChoice {
+    fn from(ns: &str) -> NSChoice {
+        NSChoice::OneOf(ns.to_owned())
+    }
+}
+
+impl From<Vec<String>> for NSChoice {
+    fn from(choices: Vec<String>) -> NSChoice {
+        NSChoice::AnyOf(choices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NSChoice;
+
+    #[test]
+    fn test_none_matches_only_no_namespace() {
+        assert!(NSChoice::None.matches(None));
+        assert!(!NSChoice::None.matches(Some("jabber:client")));
+    }
+
+    #[test]
+    fn test_one_of_matches_exact_namespace_only() {
+        let choice = NSChoice::OneOf("jabber:client".to_owned());
+        assert!(choice.matches(Some("jabber:client")));
+        assert!(!choice.matches(Some("jabber:server")));
+        assert!(!choice.matches(None));
+    }
+
+    #[test]
+    fn test_any_of_matches_any_listed_namespace() {
+        let choice = NSChoice::AnyOf(vec!["jabber:client".to_owned(), "jabber:server".to_owned()]);
+        assert!(choice.matches(Some("jabber:client")));
+        assert!(choice.matches(Some("jabber:server")));
+        assert!(!choice.matches(Some("jabber:component:accept")));
+        assert!(!choice.matches(None));
+    }
+
+    #[test]
+    fn test_any_matches_everything_including_none() {
+        assert!(NSChoice::Any.matches(Some("jabber:client")));
+        assert!(NSChoice::Any.matches(None));
+    }
+
+    #[test]
+    fn test_from_option_str() {
+        assert_eq!(NSChoice::from(None::<&str>), NSChoice::None);
+        assert_eq!(
+            NSChoice::from(Some("jabber:client")),
+            NSChoice::OneOf("jabber:client".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(NSChoice::from("jabber:client"), NSChoice::OneOf("jabber:client".to_owned()));
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let choices = vec!["a".to_owned(), "b".to_owned()];
+        assert_eq!(NSChoice::from(choices.clone()), NSChoice::AnyOf(choices));
+    }
+}
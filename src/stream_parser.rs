@@ -0,0 +1,133 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A chunked pull-parser that yields complete top-level `Element`s, mirroring
+//! rxml's `PullDriver` feed/pull model: push bytes in as they arrive, pull
+//! finished elements out as soon as their closing tag is seen, with partial
+//! state retained across chunk boundaries.
+
+use std::fmt;
+
+use crate::element::Element;
+use crate::element_builder::{BuilderError, ElementBuilder};
+use crate::parser::Parser;
+
+/// Errors produced while pulling `Element`s out of a [`StreamParser`].
+#[derive(Debug)]
+pub enum StreamError {
+    /// The fed-in bytes were not well-formed XML.
+    Parse(String),
+    /// `ElementBuilder` rejected an otherwise well-formed event sequence
+    /// (e.g. mismatched tags, or no top-level element at all).
+    Builder(BuilderError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Parse(msg) => write!(f, "malformed XML: {}", msg),
+            StreamError::Builder(err) => write!(f, "{:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<BuilderError> for StreamError {
+    fn from(err: BuilderError) -> StreamError {
+        StreamError::Builder(err)
+    }
+}
+
+/// Wraps a `Parser` + `ElementBuilder` pair so a caller can feed an XML
+/// document in arbitrary byte slices and pull out each top-level `Element` as
+/// soon as it is complete.
+///
+/// ```ignore
+/// let mut stream = StreamParser::new();
+/// stream.push("<a><b/>");
+/// stream.push("</a><c/>");
+/// for elem in &mut stream {
+///     let elem = elem?;
+///     // ...
+/// }
+/// ```
+pub struct StreamParser {
+    parser: Parser,
+    builder: ElementBuilder,
+}
+
+impl StreamParser {
+    /// Creates an empty `StreamParser` with no data fed in yet.
+    pub fn new() -> StreamParser {
+        StreamParser {
+            parser: Parser::new(),
+            builder: ElementBuilder::new(),
+        }
+    }
+
+    /// Feeds another chunk of the document in. Chunks do not need to align
+    /// with tag or element boundaries.
+    pub fn push(&mut self, chunk: &str) {
+        self.parser.feed_str(chunk);
+    }
+}
+
+impl Default for StreamParser {
+    fn default() -> StreamParser {
+        StreamParser::new()
+    }
+}
+
+impl Iterator for StreamParser {
+    type Item = Result<Element, StreamError>;
+
+    fn next(&mut self) -> Option<Result<Element, StreamError>> {
+        let builder = &mut self.builder;
+        loop {
+            match self.parser.next()? {
+                Ok(event) => {
+                    if let Some(result) = builder.handle_event(event.0) {
+                        return Some(result.map_err(StreamError::Builder));
+                    }
+                }
+                Err(err) => return Some(Err(StreamError::Parse(format!("{:?}", err)))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_input_yields_element() {
+        let mut stream = StreamParser::new();
+        stream.push("<a><b/></a>");
+        assert!(matches!(stream.next(), Some(Ok(_))));
+    }
+
+    #[test]
+    fn test_malformed_input_yields_err_instead_of_panicking() {
+        let mut stream = StreamParser::new();
+        stream.push("<a><b></a>");
+        assert!(matches!(stream.next(), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_chunk_boundaries_do_not_matter() {
+        let mut stream = StreamParser::new();
+        stream.push("<a><b/");
+        stream.push("></a><c/>");
+        assert!(matches!(stream.next(), Some(Ok(_))));
+        assert!(matches!(stream.next(), Some(Ok(_))));
+        assert!(stream.next().is_none());
+    }
+}
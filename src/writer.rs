@@ -0,0 +1,223 @@
+// RustyXML
+// Copyright 2013-2016 RustyXML developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An item-emitting encoder for `Element`, modeled on xmpp-rs/minidom's
+//! `CustomItemWriter`. Walking the tree yields a flat stream of start-element /
+//! attribute / text / end-element items instead of building up a `String`, so
+//! both `fmt::Display` and the `io::Write` streaming path share one
+//! implementation.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+use crate::element::Element;
+use crate::{escape, Xml};
+
+/// One step of the flattened item stream produced while walking an `Element`.
+enum Item<'a> {
+    StartElementOpen {
+        prefix: Option<&'a str>,
+        name: &'a str,
+    },
+    Xmlns(&'a str),
+    Attribute {
+        prefix: Option<&'a str>,
+        name: &'a str,
+        value: &'a str,
+    },
+    StartElementClose,
+    SelfClose,
+    Text(&'a str),
+    CData(&'a str),
+    Comment(&'a str),
+    PI(&'a str),
+    EndElement {
+        prefix: Option<&'a str>,
+        name: &'a str,
+    },
+}
+
+/// Writes each `Item` produced by walking `elem` to `out`.
+fn walk<W: io::Write>(
+    elem: &Element,
+    parent: Option<&Element>,
+    prefixes: &HashMap<String, String>,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut prefixes = prefixes.clone();
+    prefixes.extend(elem.prefixes.clone());
+
+    let prefix = if elem.ns != elem.default_ns {
+        Some(
+            prefixes
+                .get(elem.ns.as_ref().map_or("", |x| &x[..]))
+                .expect("No namespace prefix bound")
+                .as_str(),
+        )
+    } else {
+        None
+    };
+
+    emit(Item::StartElementOpen {
+        prefix,
+        name: &elem.name,
+    }, out)?;
+
+    let needs_xmlns = !elem.attributes.iter().any(|((name, _), _)| name == "xmlns");
+    if needs_xmlns {
+        // Only re-declare the default namespace when it actually changes.
+        match (parent, &elem.default_ns) {
+            (None, Some(ns)) => emit(Item::Xmlns(ns), out)?,
+            (Some(parent), ns) if parent.default_ns != *ns => {
+                emit(Item::Xmlns(ns.as_ref().map_or("", |x| &x[..])), out)?
+            }
+            _ => (),
+        }
+    }
+
+    for ((name, ns), value) in &elem.attributes {
+        let prefix = ns
+            .as_ref()
+            .map(|ns| prefixes.get(ns).expect("No namespace prefix bound").as_str());
+        emit(
+            Item::Attribute {
+                prefix,
+                name,
+                value,
+            },
+            out,
+        )?;
+    }
+
+    if elem.children.is_empty() {
+        emit(Item::SelfClose, out)?;
+    } else {
+        emit(Item::StartElementClose, out)?;
+        for child in &elem.children {
+            match child {
+                Xml::ElementNode(child) => walk(child, Some(elem), &prefixes, out)?,
+                Xml::CharacterNode(text) => emit(Item::Text(text), out)?,
+                Xml::CDATANode(text) => emit(Item::CData(text), out)?,
+                Xml::CommentNode(text) => emit(Item::Comment(text), out)?,
+                Xml::PINode(text) => emit(Item::PI(text), out)?,
+            }
+        }
+        emit(Item::EndElement { prefix, name: &elem.name }, out)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a single `Item` as its final XML bytes.
+fn emit<W: io::Write>(item: Item, out: &mut W) -> io::Result<()> {
+    match item {
+        Item::StartElementOpen { prefix, name } => match prefix {
+            Some(prefix) => write!(out, "<{}:{}", prefix, name),
+            None => write!(out, "<{}", name),
+        },
+        Item::Xmlns(ns) => write!(out, " xmlns='{}'", ns),
+        Item::Attribute {
+            prefix,
+            name,
+            value,
+        } => match prefix {
+            Some(prefix) => write!(out, " {}:{}='{}'", prefix, name, escape(value)),
+            None => write!(out, " {}='{}'", name, escape(value)),
+        },
+        Item::StartElementClose => write!(out, ">"),
+        Item::SelfClose => write!(out, "/>"),
+        Item::Text(text) => write!(out, "{}", escape(text)),
+        Item::CData(text) => write!(out, "<![CDATA[{}]]>", text),
+        Item::Comment(text) => write!(out, "<!--{}-->", text),
+        Item::PI(text) => write!(out, "<?{}?>", text),
+        Item::EndElement { prefix, name } => match prefix {
+            Some(prefix) => write!(out, "</{}:{}>", prefix, name),
+            None => write!(out, "</{}>", name),
+        },
+    }
+}
+
+/// Streams `elem` (and its children) to `w` without buffering the rendered
+/// string.
+pub(crate) fn write_to<W: io::Write>(elem: &Element, w: &mut W) -> io::Result<()> {
+    walk(elem, None, &HashMap::new(), w)
+}
+
+/// Like [`write_to`], prefixed with an `<?xml?>` declaration.
+pub(crate) fn write_to_decl<W: io::Write>(elem: &Element, w: &mut W) -> io::Result<()> {
+    write!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    write_to(elem, w)
+}
+
+/// An `io::Write` adapter over an `fmt::Formatter`, so `fmt::Display` can reuse
+/// the same item-emitting walk used by the streaming writer.
+pub(crate) struct FmtAdapter<'a, 'b: 'a>(pub(crate) &'a mut fmt::Formatter<'b>);
+
+impl<'a, 'b> io::Write for FmtAdapter<'a, 'b> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0
+            .write_str(s)
+            .map_err(|_| io::Error::other("formatter error"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::Element;
+
+    fn write(elem: &Element) -> String {
+        let mut out = Vec::new();
+        elem.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_self_closing() {
+        let elem = Element::new("a".to_owned(), None, vec![]);
+        assert_eq!(write(&elem), "<a/>");
+    }
+
+    #[test]
+    fn test_write_attributes_and_children() {
+        let elem: Element = "<a href='/'><b/><c/></a>".parse().unwrap();
+        assert_eq!(write(&elem), "<a href='/'><b/><c/></a>");
+    }
+
+    #[test]
+    fn test_write_escapes_text() {
+        let mut elem = Element::new("a".to_owned(), None, vec![]);
+        elem.text("<tag> & more".to_owned());
+        assert_eq!(write(&elem), "<a>&lt;tag&gt; &amp; more</a>");
+    }
+
+    #[test]
+    fn test_write_to_decl_prefixes_xml_declaration() {
+        let elem = Element::new("a".to_owned(), None, vec![]);
+        let mut out = Vec::new();
+        elem.write_to_decl(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><a/>"
+        );
+    }
+
+    #[test]
+    fn test_write_round_trips_through_parse() {
+        let original = "<a href='/'><b>hi</b></a>";
+        let elem: Element = original.parse().unwrap();
+        assert_eq!(write(&elem), original);
+    }
+}